@@ -2,11 +2,17 @@
 // SPDX-License-Identifier: Apache-2.0
 
 use crate::views::{TypeView, ValueView};
-use move_binary_format::errors::PartialVMResult;
+use move_binary_format::errors::{PartialVMError, PartialVMResult};
 use move_core_types::{
-    gas_algebra::{InternalGas, NumArgs, NumBytes},
-    language_storage::ModuleId,
+    gas_algebra::{
+        AbstractMemorySize, InternalGas, InternalGasPerAbstractMemoryUnit, InternalGasPerByte,
+        NumArgs, NumBytes,
+    },
+    language_storage::{ModuleId, TypeTag},
+    vm_status::StatusCode,
 };
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// Enum of instructions that do not need extra information for gas metering.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -67,6 +73,45 @@ pub enum SimpleInstruction {
     Abort,
 }
 
+/// Number of `SimpleInstruction` variants. `CostTable::instruction_table` must have at least
+/// this many entries for `instr as usize` to be a valid index into it.
+pub const NUM_SIMPLE_INSTRUCTIONS: usize = SimpleInstruction::Abort as usize + 1;
+
+/// A structured, finalize-time breakdown of where the gas charged over a session went.
+///
+/// `execution_gas_used` and `storage_gas_used` are what was actually deducted from the meter's
+/// balance; `storage_rebate` is credited back separately (it is not subtracted from either of
+/// those two) so that an embedder can report "gas used", "gas refunded for freed state", and the
+/// net charge as distinct line items on a transaction receipt, the same way other metered VMs
+/// split refunds from the base charge.
+///
+/// When a charge fails with `OUT_OF_GAS`, `ChargingGasMeter` zeroes its balance but adds the
+/// forfeited remainder to none of these fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasOutputs {
+    pub execution_gas_used: InternalGas,
+    pub storage_gas_used: InternalGas,
+    pub storage_rebate: InternalGas,
+    /// Gas charged but not attributable to execution or storage, e.g. from an embedder that
+    /// rounds charges up to fixed-size billing units. `ChargingGasMeter` charges exact amounts,
+    /// so this is always zero for it.
+    pub burn: InternalGas,
+}
+
+impl GasOutputs {
+    /// The net amount the transaction should actually be billed: what was charged, minus what
+    /// was refunded for state it freed. `storage_rebate` is expected to never exceed what was
+    /// charged (`ChargingGasMeter` enforces this via `max_storage_rebate_fraction_percent`), but
+    /// the subtraction saturates at zero rather than panicking if that invariant is ever
+    /// violated by some other `GasMeter` implementation.
+    pub fn net_gas_used(&self) -> InternalGas {
+        let charged = u64::from(self.execution_gas_used)
+            .saturating_add(u64::from(self.storage_gas_used))
+            .saturating_add(u64::from(self.burn));
+        InternalGas::new(charged.saturating_sub(u64::from(self.storage_rebate)))
+    }
+}
+
 /// Trait that defines a generic gas meter interface, allowing clients of the Move VM to implement
 /// their own metering scheme.
 pub trait GasMeter {
@@ -193,13 +238,69 @@ pub trait GasMeter {
     /// session -- identical transactions can have different gas costs. Use at your own risk.
     fn charge_load_resource(&mut self, loaded: Option<NumBytes>) -> PartialVMResult<()>;
 
-    /// Charge for executing a native function.
-    /// The cost is calculated returned by the native function implementation.
+    /// Charge for executing a native function whose total cost is known up front.
+    /// The cost is calculated and returned by the native function implementation.
     /// Should fail if not enough gas units are left.
     ///
-    /// In the future, we may want to remove this and directly pass a reference to the GasMeter
-    /// instance to the native functions to allow gas to be deducted during computation.
+    /// For a native whose cost depends on how much work it ends up doing (hashing,
+    /// serialization, bignum math), `charge_native_step` is the incremental counterpart: it
+    /// charges in pieces as the work proceeds, so a caller holding the meter can stop
+    /// mid-execution instead of charging a single lump sum after unbounded work (see its note on
+    /// who can call it).
     fn charge_native_function(&mut self, amount: InternalGas) -> PartialVMResult<()>;
+
+    /// Charge an incremental amount of gas from inside a running native function. Unlike
+    /// `charge_native_function`, this can be called multiple times over the course of a single
+    /// native call, so the native can fail out -- returning only the partial charge incurred so
+    /// far -- the moment the balance hits zero, rather than doing unbounded work before a single
+    /// post-hoc charge discovers the gas was already exhausted.
+    ///
+    /// NOTE: nothing in this crate passes a handle to the enclosing meter into native function
+    /// implementations (the native dispatch path lives outside `move-vm-types`), so natives do
+    /// not call this themselves; only code that holds the meter can.
+    fn charge_native_step(&mut self, amount: InternalGas) -> PartialVMResult<()>;
+
+    /// Charge for pushing a value onto the operand stack. Implementations that want to bound
+    /// interpreter memory should use this (together with `charge_pop`) to track how much
+    /// "abstract memory" the operand stack currently holds, and fail with `OUT_OF_GAS` before a
+    /// push would grow it past a configured limit -- this catches a script that pushes huge
+    /// values before the host ever has to allocate for them.
+    fn charge_push(&mut self, pushed: impl ValueView) -> PartialVMResult<()>;
+
+    /// Charge for popping a value off the operand stack. Mirrors `charge_push`: implementations
+    /// that track abstract memory usage should shrink their running total here.
+    fn charge_pop(&mut self, popped: impl ValueView) -> PartialVMResult<()>;
+
+    /// Charge for binding a new call frame, given the resulting call-stack depth and the total
+    /// abstract size of the locals the frame starts with. Lets an implementation enforce a
+    /// maximum call-stack height and account for locals the same way `charge_push`/`charge_pop`
+    /// account for the operand stack, so that deep recursion fails deterministically with
+    /// `OUT_OF_GAS` rather than by overflowing the host stack.
+    fn charge_call_frame(
+        &mut self,
+        call_stack_height: NumArgs,
+        locals_size: AbstractMemorySize,
+    ) -> PartialVMResult<()>;
+
+    /// Charge for releasing a call frame on return, given the total abstract size of the
+    /// locals it held. Mirrors `charge_call_frame`/`charge_pop`: implementations that track
+    /// abstract memory usage must shrink their running total here, or every call -- not just
+    /// nested recursion -- would ratchet it up for the rest of the session.
+    fn charge_pop_frame(&mut self, locals_size: AbstractMemorySize) -> PartialVMResult<()>;
+
+    /// Consumes the meter and reports a structured breakdown of where the gas it charged went,
+    /// mirroring the refund/burn split used by other metered VMs. Embedders should use this
+    /// instead of a single opaque "units consumed" number so that a transaction receipt can show
+    /// gas used, gas refunded for freed state, and the net charge separately.
+    fn finish(self) -> GasOutputs;
+
+    /// Best-effort visibility into how much gas is left. Used by tooling -- e.g. `GasProfiler`,
+    /// to measure the delta a single `charge_*` call consumed -- rather than by the interpreter
+    /// itself, which only cares whether a charge succeeded. Implementations that don't track a
+    /// balance can leave the default, which reports zero.
+    fn remaining_gas(&self) -> InternalGas {
+        InternalGas::new(0)
+    }
 }
 
 /// A dummy gas meter that does not meter anything.
@@ -372,4 +473,1096 @@ impl GasMeter for UnmeteredGasMeter {
     fn charge_native_function(&mut self, _amount: InternalGas) -> PartialVMResult<()> {
         Ok(())
     }
+
+    fn charge_native_step(&mut self, _amount: InternalGas) -> PartialVMResult<()> {
+        Ok(())
+    }
+
+    fn charge_push(&mut self, _pushed: impl ValueView) -> PartialVMResult<()> {
+        Ok(())
+    }
+
+    fn charge_pop(&mut self, _popped: impl ValueView) -> PartialVMResult<()> {
+        Ok(())
+    }
+
+    fn charge_call_frame(
+        &mut self,
+        _call_stack_height: NumArgs,
+        _locals_size: AbstractMemorySize,
+    ) -> PartialVMResult<()> {
+        Ok(())
+    }
+
+    fn charge_pop_frame(&mut self, _locals_size: AbstractMemorySize) -> PartialVMResult<()> {
+        Ok(())
+    }
+
+    fn finish(self) -> GasOutputs {
+        GasOutputs::default()
+    }
+}
+
+/// The cost of a single metered operation, expressed as a fixed base charge plus two variable
+/// terms: one scaling with the serialized byte size of the operands (`per_byte`) and one
+/// scaling with their abstract, in-memory size (`per_abstract_mem_unit`). Splitting the cost
+/// this way lets a schedule charge bytecode that only touches small scalars far less than one
+/// that moves large vectors or structs, without special-casing every instruction.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GasCost {
+    pub base: InternalGas,
+    pub per_byte: InternalGasPerByte,
+    pub per_abstract_mem_unit: InternalGasPerAbstractMemoryUnit,
+}
+
+impl GasCost {
+    pub fn new(
+        base: InternalGas,
+        per_byte: InternalGasPerByte,
+        per_abstract_mem_unit: InternalGasPerAbstractMemoryUnit,
+    ) -> Self {
+        Self {
+            base,
+            per_byte,
+            per_abstract_mem_unit,
+        }
+    }
+
+    /// Total charge for an operation whose operands have the given serialized byte size and
+    /// abstract memory size.
+    pub fn total(&self, size_in_bytes: NumBytes, abstract_size: AbstractMemorySize) -> InternalGas {
+        self.base + self.per_byte * size_in_bytes + self.per_abstract_mem_unit * abstract_size
+    }
+}
+
+/// An on-chain, governance-updatable gas schedule.
+///
+/// A `CostTable` is pure data: it is meant to be read once per block from a resource published
+/// at a well-known address (typically alongside the genesis blob) and handed to a
+/// [`ChargingGasMeter`]. Because it holds no compiled logic, the schedule it encodes can be
+/// amended by a governance transaction -- e.g. to re-price an instruction that turns out to be
+/// underpriced in practice -- without requiring a VM upgrade.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostTable {
+    /// Cost of every `SimpleInstruction`, indexed by `instr as usize`.
+    pub instruction_table: Vec<GasCost>,
+
+    /// Flat dispatch overhead (`base` only) charged on every `GasMeter::charge_native_function`
+    /// call, ahead of the amount the native reports; `charge_native_step` does not charge it.
+    pub native_call: GasCost,
+
+    pub call: GasCost,
+    pub call_generic: GasCost,
+    pub ld_const: GasCost,
+    pub copy_loc: GasCost,
+    pub move_loc: GasCost,
+    pub store_loc: GasCost,
+    pub pack: GasCost,
+    pub unpack: GasCost,
+    pub read_ref: GasCost,
+    pub write_ref: GasCost,
+    pub eq: GasCost,
+    pub neq: GasCost,
+    pub borrow_global: GasCost,
+    pub exists: GasCost,
+    pub move_from: GasCost,
+    pub move_to: GasCost,
+    pub vec_pack: GasCost,
+    pub vec_len: GasCost,
+    pub vec_borrow: GasCost,
+    pub vec_push_back: GasCost,
+    pub vec_pop_back: GasCost,
+    pub vec_unpack: GasCost,
+    pub vec_swap: GasCost,
+
+    /// Cost of loading a resource from storage, keyed on the number of bytes loaded.
+    pub load_resource: GasCost,
+
+    /// Gas charged per unit of abstract memory added to the operand stack or bound into a call
+    /// frame's locals; see `GasMeter::charge_push`/`charge_pop`/`charge_call_frame`.
+    pub per_abstract_memory_unit: InternalGasPerAbstractMemoryUnit,
+
+    /// Upper bound on the total abstract memory the operand stack and current frames may hold
+    /// at once, enforced by `ChargingGasMeter` on every push and call.
+    pub max_abstract_memory_in_use: AbstractMemorySize,
+
+    /// Upper bound on the operand stack height, enforced on every push.
+    pub max_operand_stack_height: u64,
+
+    /// Upper bound on the call stack height, enforced on every call.
+    pub max_call_stack_height: u64,
+
+    /// Gas credited back per unit of abstract memory freed by deleting a resource from storage
+    /// (see `GasMeter::charge_move_from`). Purely in-memory operations -- e.g. `VecUnpack`,
+    /// which only ever moves values between the operand stack and a vector -- never touch
+    /// storage and so never accrue a rebate, no matter how many elements they move.
+    pub storage_refund_per_abstract_memory_unit: InternalGasPerAbstractMemoryUnit,
+
+    /// Caps the total storage rebate as a percentage of all gas charged so far, so that
+    /// deleting state can never make a transaction net gas-negative. Must be at most 100;
+    /// `ChargingGasMeter::new` rejects a table that violates this.
+    pub max_storage_rebate_fraction_percent: u64,
+}
+
+fn abstract_size_sum(args: impl ExactSizeIterator<Item = impl ValueView>) -> AbstractMemorySize {
+    args.fold(AbstractMemorySize::new(0), |acc, val| {
+        acc + val.legacy_abstract_memory_size()
+    })
+}
+
+/// A `GasMeter` backed by a runtime-supplied [`CostTable`].
+///
+/// Every `charge_*` call looks up the relevant entry in the cost table, multiplies its
+/// variable terms by the size of the operands it was handed, and debits the result from a
+/// running balance -- failing with `StatusCode::OUT_OF_GAS` the moment the balance would go
+/// negative. This is the standard on-chain metering setup: the schedule lives in the
+/// `CostTable` (loadable from a resource and swappable by governance) while this struct only
+/// knows how to apply it.
+pub struct ChargingGasMeter {
+    cost_table: CostTable,
+    balance: InternalGas,
+
+    /// Abstract memory currently held by the operand stack plus the locals of every live call
+    /// frame, tracked so pushes/binds can be rejected once `max_abstract_memory_in_use` would be
+    /// exceeded rather than after the host has already allocated for them.
+    abstract_memory_in_use: AbstractMemorySize,
+    operand_stack_height: u64,
+
+    /// The most recent absolute call-stack depth reported to `charge_call_frame` by the caller.
+    /// Unlike `operand_stack_height`, this isn't incremented/decremented locally: the interpreter
+    /// already knows the resulting depth at every call and passes it in directly, so
+    /// `charge_pop_frame` has nothing to adjust here on return.
+    call_stack_height: u64,
+
+    /// Total gas ever deducted from `balance`, kept around (even though `balance` already
+    /// reflects what's left) so the storage-rebate cap can be expressed as a fraction of it.
+    total_charged: InternalGas,
+    execution_gas_used: InternalGas,
+    storage_gas_used: InternalGas,
+    storage_rebate: InternalGas,
+}
+
+impl ChargingGasMeter {
+    /// Builds a meter from a `CostTable` sourced from governance/genesis data. Fails rather
+    /// than panicking later if the table is malformed, e.g. missing entries for some
+    /// `SimpleInstruction` variant.
+    pub fn new(cost_table: CostTable, budget: InternalGas) -> PartialVMResult<Self> {
+        if cost_table.instruction_table.len() < NUM_SIMPLE_INSTRUCTIONS {
+            return Err(PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR)
+                .with_message(format!(
+                    "CostTable.instruction_table has {} entries, but {} are required to cover every SimpleInstruction variant",
+                    cost_table.instruction_table.len(),
+                    NUM_SIMPLE_INSTRUCTIONS,
+                )));
+        }
+        if cost_table.max_storage_rebate_fraction_percent > 100 {
+            return Err(
+                PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR).with_message(
+                    format!(
+                        "CostTable.max_storage_rebate_fraction_percent must be <= 100, got {}",
+                        cost_table.max_storage_rebate_fraction_percent,
+                    ),
+                ),
+            );
+        }
+        Ok(Self {
+            cost_table,
+            balance: budget,
+            abstract_memory_in_use: AbstractMemorySize::new(0),
+            operand_stack_height: 0,
+            call_stack_height: 0,
+            total_charged: InternalGas::new(0),
+            execution_gas_used: InternalGas::new(0),
+            storage_gas_used: InternalGas::new(0),
+            storage_rebate: InternalGas::new(0),
+        })
+    }
+
+    /// Gas units remaining in the meter.
+    pub fn balance(&self) -> InternalGas {
+        self.balance
+    }
+
+    fn deduct(&mut self, amount: InternalGas) -> PartialVMResult<()> {
+        if amount > self.balance {
+            self.balance = InternalGas::new(0);
+            return Err(PartialVMError::new(StatusCode::OUT_OF_GAS));
+        }
+        self.balance = self.balance - amount;
+        self.total_charged = self.total_charged + amount;
+        Ok(())
+    }
+
+    /// Deducts `amount` and attributes it to execution gas in the final `GasOutputs`.
+    fn charge_execution(&mut self, amount: InternalGas) -> PartialVMResult<()> {
+        self.deduct(amount)?;
+        self.execution_gas_used = self.execution_gas_used + amount;
+        Ok(())
+    }
+
+    /// Deducts `amount` and attributes it to storage gas in the final `GasOutputs`.
+    fn charge_storage(&mut self, amount: InternalGas) -> PartialVMResult<()> {
+        self.deduct(amount)?;
+        self.storage_gas_used = self.storage_gas_used + amount;
+        Ok(())
+    }
+
+    fn charge_with(
+        &mut self,
+        cost: GasCost,
+        size_in_bytes: NumBytes,
+        abstract_size: AbstractMemorySize,
+    ) -> PartialVMResult<()> {
+        self.charge_execution(cost.total(size_in_bytes, abstract_size))
+    }
+
+    /// Credits back gas proportional to the abstract size of a storage slot just freed by
+    /// deleting a resource, capped at `max_storage_rebate_fraction_percent` of all gas charged
+    /// so far.
+    fn accrue_storage_refund(&mut self, freed_size: AbstractMemorySize) {
+        // `new` already validates `max_storage_rebate_fraction_percent <= 100`, so this division
+        // can't make `cap` exceed `total_charged`; the multiply is done in u128 first since
+        // `total_charged` can approach u64::MAX and times the percentage would overflow u64.
+        let raw = u64::from(self.cost_table.storage_refund_per_abstract_memory_unit)
+            .saturating_mul(u64::from(freed_size));
+        let cap = (u128::from(self.total_charged)
+            * u128::from(self.cost_table.max_storage_rebate_fraction_percent)
+            / 100) as u64;
+        let new_total = u64::from(self.storage_rebate).saturating_add(raw).min(cap);
+        self.storage_rebate = InternalGas::new(new_total);
+    }
+
+    /// Grows the running abstract-memory total by `size`, charging for the increase and
+    /// failing with `OUT_OF_GAS` if the new total would exceed the configured maximum.
+    fn grow_abstract_memory(&mut self, size: AbstractMemorySize) -> PartialVMResult<()> {
+        self.abstract_memory_in_use = self.abstract_memory_in_use + size;
+        if self.abstract_memory_in_use > self.cost_table.max_abstract_memory_in_use {
+            return Err(PartialVMError::new(StatusCode::OUT_OF_GAS));
+        }
+        let amount = self.cost_table.per_abstract_memory_unit * size;
+        self.charge_execution(amount)
+    }
+
+    fn shrink_abstract_memory(&mut self, size: AbstractMemorySize) {
+        self.abstract_memory_in_use = AbstractMemorySize::new(
+            u64::from(self.abstract_memory_in_use).saturating_sub(u64::from(size)),
+        );
+    }
+}
+
+impl GasMeter for ChargingGasMeter {
+    fn charge_simple_instr(&mut self, instr: SimpleInstruction) -> PartialVMResult<()> {
+        // `new` already validates that the table covers every variant, but don't trust that
+        // invariant blindly this far from construction -- fail instead of panicking if it's
+        // ever violated (e.g. a future variant is added without updating the table).
+        let cost = *self
+            .cost_table
+            .instruction_table
+            .get(instr as usize)
+            .ok_or_else(|| PartialVMError::new(StatusCode::UNKNOWN_INVARIANT_VIOLATION_ERROR))?;
+        self.charge_with(cost, NumBytes::new(0), AbstractMemorySize::new(0))
+    }
+
+    fn charge_call(
+        &mut self,
+        _module_id: &ModuleId,
+        _func_name: &str,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let size = abstract_size_sum(args);
+        self.charge_with(self.cost_table.call, NumBytes::new(0), size)
+    }
+
+    fn charge_call_generic(
+        &mut self,
+        _module_id: &ModuleId,
+        _func_name: &str,
+        _ty_args: impl ExactSizeIterator<Item = impl TypeView>,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let size = abstract_size_sum(args);
+        self.charge_with(self.cost_table.call_generic, NumBytes::new(0), size)
+    }
+
+    fn charge_ld_const(&mut self, size: NumBytes) -> PartialVMResult<()> {
+        self.charge_with(self.cost_table.ld_const, size, AbstractMemorySize::new(0))
+    }
+
+    fn charge_copy_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        let size = val.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.copy_loc, NumBytes::new(0), size)
+    }
+
+    fn charge_move_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        let size = val.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.move_loc, NumBytes::new(0), size)
+    }
+
+    fn charge_store_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        let size = val.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.store_loc, NumBytes::new(0), size)
+    }
+
+    fn charge_pack(
+        &mut self,
+        _is_generic: bool,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let size = abstract_size_sum(args);
+        self.charge_with(self.cost_table.pack, NumBytes::new(0), size)
+    }
+
+    fn charge_unpack(
+        &mut self,
+        _is_generic: bool,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let size = abstract_size_sum(args);
+        self.charge_with(self.cost_table.unpack, NumBytes::new(0), size)
+    }
+
+    fn charge_read_ref(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        let size = val.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.read_ref, NumBytes::new(0), size)
+    }
+
+    fn charge_write_ref(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        let size = val.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.write_ref, NumBytes::new(0), size)
+    }
+
+    fn charge_eq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
+        let size = lhs.legacy_abstract_memory_size() + rhs.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.eq, NumBytes::new(0), size)
+    }
+
+    fn charge_neq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
+        let size = lhs.legacy_abstract_memory_size() + rhs.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.neq, NumBytes::new(0), size)
+    }
+
+    fn charge_borrow_global(
+        &mut self,
+        _is_mut: bool,
+        _is_generic: bool,
+        _ty: impl TypeView,
+        _is_success: bool,
+    ) -> PartialVMResult<()> {
+        let cost = self.cost_table.borrow_global;
+        self.charge_with(cost, NumBytes::new(0), AbstractMemorySize::new(0))
+    }
+
+    fn charge_exists(
+        &mut self,
+        _is_generic: bool,
+        _ty: impl TypeView,
+        _exists: bool,
+    ) -> PartialVMResult<()> {
+        let cost = self.cost_table.exists;
+        self.charge_with(cost, NumBytes::new(0), AbstractMemorySize::new(0))
+    }
+
+    fn charge_move_from(
+        &mut self,
+        _is_generic: bool,
+        _ty: impl TypeView,
+        val: Option<impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let is_deletion = val.is_some();
+        let size = val
+            .map(|val| val.legacy_abstract_memory_size())
+            .unwrap_or_else(|| AbstractMemorySize::new(0));
+        self.charge_with(self.cost_table.move_from, NumBytes::new(0), size)?;
+        if is_deletion {
+            self.accrue_storage_refund(size);
+        }
+        Ok(())
+    }
+
+    fn charge_move_to(
+        &mut self,
+        _is_generic: bool,
+        _ty: impl TypeView,
+        val: impl ValueView,
+        _is_success: bool,
+    ) -> PartialVMResult<()> {
+        let size = val.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.move_to, NumBytes::new(0), size)
+    }
+
+    fn charge_vec_pack<'a>(
+        &mut self,
+        _ty: impl TypeView + 'a,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let size = abstract_size_sum(args);
+        self.charge_with(self.cost_table.vec_pack, NumBytes::new(0), size)
+    }
+
+    fn charge_vec_len(&mut self, _ty: impl TypeView) -> PartialVMResult<()> {
+        let cost = self.cost_table.vec_len;
+        self.charge_with(cost, NumBytes::new(0), AbstractMemorySize::new(0))
+    }
+
+    fn charge_vec_borrow(
+        &mut self,
+        _is_mut: bool,
+        _ty: impl TypeView,
+        _is_success: bool,
+    ) -> PartialVMResult<()> {
+        let cost = self.cost_table.vec_borrow;
+        self.charge_with(cost, NumBytes::new(0), AbstractMemorySize::new(0))
+    }
+
+    fn charge_vec_push_back(
+        &mut self,
+        _ty: impl TypeView,
+        val: impl ValueView,
+    ) -> PartialVMResult<()> {
+        let size = val.legacy_abstract_memory_size();
+        self.charge_with(self.cost_table.vec_push_back, NumBytes::new(0), size)
+    }
+
+    fn charge_vec_pop_back(
+        &mut self,
+        _ty: impl TypeView,
+        val: Option<impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let size = val
+            .map(|val| val.legacy_abstract_memory_size())
+            .unwrap_or_else(|| AbstractMemorySize::new(0));
+        self.charge_with(self.cost_table.vec_pop_back, NumBytes::new(0), size)
+    }
+
+    fn charge_vec_unpack(
+        &mut self,
+        _ty: impl TypeView,
+        expect_num_elements: NumArgs,
+    ) -> PartialVMResult<()> {
+        // Unpacking only ever touches the operand stack, never storage -- the elements end up
+        // back on the stack as individual values, not freed. So this charges for the work
+        // (scaled by element count, since that's what the trait gives us) but grants no storage
+        // rebate; see `CostTable::storage_refund_per_abstract_memory_unit`.
+        let cost = self.cost_table.vec_unpack;
+        let size = AbstractMemorySize::new(u64::from(expect_num_elements));
+        let amount = cost.base + cost.per_abstract_mem_unit * size;
+        self.charge_execution(amount)
+    }
+
+    fn charge_vec_swap(&mut self, _ty: impl TypeView) -> PartialVMResult<()> {
+        let cost = self.cost_table.vec_swap;
+        self.charge_with(cost, NumBytes::new(0), AbstractMemorySize::new(0))
+    }
+
+    fn charge_load_resource(&mut self, loaded: Option<NumBytes>) -> PartialVMResult<()> {
+        let size = loaded.unwrap_or_else(|| NumBytes::new(0));
+        let amount = self
+            .cost_table
+            .load_resource
+            .total(size, AbstractMemorySize::new(0));
+        self.charge_storage(amount)
+    }
+
+    fn charge_native_function(&mut self, amount: InternalGas) -> PartialVMResult<()> {
+        self.charge_execution(self.cost_table.native_call.base)?;
+        self.charge_execution(amount)
+    }
+
+    fn charge_native_step(&mut self, amount: InternalGas) -> PartialVMResult<()> {
+        self.charge_execution(amount)
+    }
+
+    fn charge_push(&mut self, pushed: impl ValueView) -> PartialVMResult<()> {
+        self.operand_stack_height += 1;
+        if self.operand_stack_height > self.cost_table.max_operand_stack_height {
+            return Err(PartialVMError::new(StatusCode::OUT_OF_GAS));
+        }
+        self.grow_abstract_memory(pushed.legacy_abstract_memory_size())
+    }
+
+    fn charge_pop(&mut self, popped: impl ValueView) -> PartialVMResult<()> {
+        self.operand_stack_height = self.operand_stack_height.saturating_sub(1);
+        self.shrink_abstract_memory(popped.legacy_abstract_memory_size());
+        Ok(())
+    }
+
+    fn charge_call_frame(
+        &mut self,
+        call_stack_height: NumArgs,
+        locals_size: AbstractMemorySize,
+    ) -> PartialVMResult<()> {
+        self.call_stack_height = u64::from(call_stack_height);
+        if self.call_stack_height > self.cost_table.max_call_stack_height {
+            return Err(PartialVMError::new(StatusCode::OUT_OF_GAS));
+        }
+        self.grow_abstract_memory(locals_size)
+    }
+
+    fn charge_pop_frame(&mut self, locals_size: AbstractMemorySize) -> PartialVMResult<()> {
+        self.shrink_abstract_memory(locals_size);
+        Ok(())
+    }
+
+    fn finish(self) -> GasOutputs {
+        GasOutputs {
+            execution_gas_used: self.execution_gas_used,
+            storage_gas_used: self.storage_gas_used,
+            storage_rebate: self.storage_rebate,
+            burn: InternalGas::new(0),
+        }
+    }
+
+    fn remaining_gas(&self) -> InternalGas {
+        self.balance
+    }
+}
+
+/// The kind of operation a single [`ProfiledEvent`] accounts for. Carries just enough
+/// information to attribute cost to a concrete call site or operand type: the called
+/// `ModuleId`/function name for `charge_call*`, and the `TypeTag` for storage and vector ops
+/// (recovered from the `TypeView` the caller passed in).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfiledOp {
+    Instruction(SimpleInstruction),
+    Call {
+        module_id: ModuleId,
+        func_name: String,
+    },
+    CallGeneric {
+        module_id: ModuleId,
+        func_name: String,
+    },
+    LdConst,
+    CopyLoc,
+    MoveLoc,
+    StoreLoc,
+    Pack,
+    Unpack,
+    ReadRef,
+    WriteRef,
+    Eq,
+    Neq,
+    BorrowGlobal(TypeTag),
+    Exists(TypeTag),
+    MoveFrom(TypeTag),
+    MoveTo(TypeTag),
+    VecPack(TypeTag),
+    VecLen(TypeTag),
+    VecBorrow(TypeTag),
+    VecPushBack(TypeTag),
+    VecPopBack(TypeTag),
+    VecUnpack(TypeTag),
+    VecSwap(TypeTag),
+    LoadResource,
+    NativeFunction,
+    NativeStep,
+    Push,
+    Pop,
+    CallFrame,
+    PopFrame,
+}
+
+impl ProfiledOp {
+    /// A short, human-readable label used to bucket events in `GasProfile::histogram`.
+    pub fn label(&self) -> String {
+        match self {
+            ProfiledOp::Instruction(instr) => format!("{:?}", instr),
+            ProfiledOp::Call {
+                module_id,
+                func_name,
+            } => format!("call({}::{})", module_id, func_name),
+            ProfiledOp::CallGeneric {
+                module_id,
+                func_name,
+            } => {
+                format!("call_generic({}::{})", module_id, func_name)
+            }
+            ProfiledOp::LdConst => "ld_const".to_string(),
+            ProfiledOp::CopyLoc => "copy_loc".to_string(),
+            ProfiledOp::MoveLoc => "move_loc".to_string(),
+            ProfiledOp::StoreLoc => "store_loc".to_string(),
+            ProfiledOp::Pack => "pack".to_string(),
+            ProfiledOp::Unpack => "unpack".to_string(),
+            ProfiledOp::ReadRef => "read_ref".to_string(),
+            ProfiledOp::WriteRef => "write_ref".to_string(),
+            ProfiledOp::Eq => "eq".to_string(),
+            ProfiledOp::Neq => "neq".to_string(),
+            ProfiledOp::BorrowGlobal(ty) => format!("borrow_global<{}>", ty),
+            ProfiledOp::Exists(ty) => format!("exists<{}>", ty),
+            ProfiledOp::MoveFrom(ty) => format!("move_from<{}>", ty),
+            ProfiledOp::MoveTo(ty) => format!("move_to<{}>", ty),
+            ProfiledOp::VecPack(ty) => format!("vec_pack<{}>", ty),
+            ProfiledOp::VecLen(ty) => format!("vec_len<{}>", ty),
+            ProfiledOp::VecBorrow(ty) => format!("vec_borrow<{}>", ty),
+            ProfiledOp::VecPushBack(ty) => format!("vec_push_back<{}>", ty),
+            ProfiledOp::VecPopBack(ty) => format!("vec_pop_back<{}>", ty),
+            ProfiledOp::VecUnpack(ty) => format!("vec_unpack<{}>", ty),
+            ProfiledOp::VecSwap(ty) => format!("vec_swap<{}>", ty),
+            ProfiledOp::LoadResource => "load_resource".to_string(),
+            ProfiledOp::NativeFunction => "native_function".to_string(),
+            ProfiledOp::NativeStep => "native_step".to_string(),
+            ProfiledOp::Push => "push".to_string(),
+            ProfiledOp::Pop => "pop".to_string(),
+            ProfiledOp::CallFrame => "call_frame".to_string(),
+            ProfiledOp::PopFrame => "pop_frame".to_string(),
+        }
+    }
+}
+
+/// A single recorded charge in a `GasProfiler`'s trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProfiledEvent {
+    /// Monotonically increasing index of this event within the session.
+    pub step: u64,
+    pub op: ProfiledOp,
+    /// How much gas this one operation consumed, measured as the inner meter's `remaining_gas`
+    /// just before the charge minus just after. For a charge that fails with `OUT_OF_GAS`, this
+    /// reflects whatever the inner meter actually deducted -- which, for a meter like
+    /// `ChargingGasMeter` that forfeits the rest of the balance the moment it goes negative, is
+    /// the entire remaining balance rather than just this operation's nominal cost.
+    pub gas_used: InternalGas,
+    /// Call-stack depth this event occurred at, reconstructed from `charge_call`/
+    /// `charge_call_generic` (which push a frame) and `charge_simple_instr(Ret)` (which pops
+    /// one). Lets a consumer fold the trace into a flamegraph keyed by call stack.
+    pub call_depth: u32,
+}
+
+/// The finished output of a `GasProfiler` session: the inner meter's usual `GasOutputs`, plus
+/// the full per-operation trace it recorded along the way.
+#[derive(Debug, Clone)]
+pub struct GasProfile {
+    pub outputs: GasOutputs,
+    pub trace: Vec<ProfiledEvent>,
+}
+
+impl GasProfile {
+    /// Sums gas used per distinct operation label, giving contract authors a breakdown of
+    /// exactly which instructions and function calls dominated a session's gas bill.
+    pub fn histogram(&self) -> BTreeMap<String, InternalGas> {
+        let mut histogram: BTreeMap<String, InternalGas> = BTreeMap::new();
+        for event in &self.trace {
+            let entry = histogram
+                .entry(event.op.label())
+                .or_insert_with(|| InternalGas::new(0));
+            *entry = *entry + event.gas_used;
+        }
+        histogram
+    }
+}
+
+/// A `GasMeter` decorator that delegates every `charge_*` call to an inner meter `M` while
+/// recording the gas delta, the kind of operation, and a monotonic step index -- without
+/// changing the behavior or the charged amount of the meter it wraps. Call `into_profile` to
+/// get the recorded trace back out, e.g. to fold it into a flamegraph or a per-opcode
+/// histogram.
+pub struct GasProfiler<M> {
+    inner: M,
+    trace: Vec<ProfiledEvent>,
+    next_step: u64,
+    call_depth: u32,
+}
+
+impl<M: GasMeter> GasProfiler<M> {
+    pub fn new(inner: M) -> Self {
+        Self {
+            inner,
+            trace: Vec::new(),
+            next_step: 0,
+            call_depth: 0,
+        }
+    }
+
+    /// The trace recorded so far.
+    pub fn trace(&self) -> &[ProfiledEvent] {
+        &self.trace
+    }
+
+    /// Consumes the profiler, finishing the inner meter and returning its `GasOutputs` together
+    /// with the full recorded trace.
+    pub fn into_profile(self) -> GasProfile {
+        GasProfile {
+            outputs: self.inner.finish(),
+            trace: self.trace,
+        }
+    }
+
+    fn record(&mut self, op: ProfiledOp, gas_used: InternalGas) {
+        let step = self.next_step;
+        self.next_step += 1;
+        let call_depth = self.call_depth;
+        self.trace.push(ProfiledEvent {
+            step,
+            op,
+            gas_used,
+            call_depth,
+        });
+    }
+
+    fn charge_and_record<T>(
+        &mut self,
+        op: ProfiledOp,
+        charge: impl FnOnce(&mut M) -> PartialVMResult<T>,
+    ) -> PartialVMResult<T> {
+        let before = self.inner.remaining_gas();
+        let result = charge(&mut self.inner);
+        let after = self.inner.remaining_gas();
+        let gas_used = InternalGas::new(u64::from(before).saturating_sub(u64::from(after)));
+        self.record(op, gas_used);
+        result
+    }
+}
+
+impl<M: GasMeter> GasMeter for GasProfiler<M> {
+    fn charge_simple_instr(&mut self, instr: SimpleInstruction) -> PartialVMResult<()> {
+        let result = self.charge_and_record(ProfiledOp::Instruction(instr), |m| {
+            m.charge_simple_instr(instr)
+        });
+        if instr == SimpleInstruction::Ret {
+            self.call_depth = self.call_depth.saturating_sub(1);
+        }
+        result
+    }
+
+    fn charge_call(
+        &mut self,
+        module_id: &ModuleId,
+        func_name: &str,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::Call {
+            module_id: module_id.clone(),
+            func_name: func_name.to_string(),
+        };
+        let result = self.charge_and_record(op, |m| m.charge_call(module_id, func_name, args));
+        self.call_depth += 1;
+        result
+    }
+
+    fn charge_call_generic(
+        &mut self,
+        module_id: &ModuleId,
+        func_name: &str,
+        ty_args: impl ExactSizeIterator<Item = impl TypeView>,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::CallGeneric {
+            module_id: module_id.clone(),
+            func_name: func_name.to_string(),
+        };
+        let result = self.charge_and_record(op, |m| {
+            m.charge_call_generic(module_id, func_name, ty_args, args)
+        });
+        self.call_depth += 1;
+        result
+    }
+
+    fn charge_ld_const(&mut self, size: NumBytes) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::LdConst, |m| m.charge_ld_const(size))
+    }
+
+    fn charge_copy_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::CopyLoc, |m| m.charge_copy_loc(val))
+    }
+
+    fn charge_move_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::MoveLoc, |m| m.charge_move_loc(val))
+    }
+
+    fn charge_store_loc(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::StoreLoc, |m| m.charge_store_loc(val))
+    }
+
+    fn charge_pack(
+        &mut self,
+        is_generic: bool,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::Pack, |m| m.charge_pack(is_generic, args))
+    }
+
+    fn charge_unpack(
+        &mut self,
+        is_generic: bool,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::Unpack, |m| m.charge_unpack(is_generic, args))
+    }
+
+    fn charge_read_ref(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::ReadRef, |m| m.charge_read_ref(val))
+    }
+
+    fn charge_write_ref(&mut self, val: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::WriteRef, |m| m.charge_write_ref(val))
+    }
+
+    fn charge_eq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::Eq, |m| m.charge_eq(lhs, rhs))
+    }
+
+    fn charge_neq(&mut self, lhs: impl ValueView, rhs: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::Neq, |m| m.charge_neq(lhs, rhs))
+    }
+
+    fn charge_borrow_global(
+        &mut self,
+        is_mut: bool,
+        is_generic: bool,
+        ty: impl TypeView,
+        is_success: bool,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::BorrowGlobal(ty.to_type_tag());
+        self.charge_and_record(op, |m| {
+            m.charge_borrow_global(is_mut, is_generic, ty, is_success)
+        })
+    }
+
+    fn charge_exists(
+        &mut self,
+        is_generic: bool,
+        ty: impl TypeView,
+        exists: bool,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::Exists(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_exists(is_generic, ty, exists))
+    }
+
+    fn charge_move_from(
+        &mut self,
+        is_generic: bool,
+        ty: impl TypeView,
+        val: Option<impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::MoveFrom(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_move_from(is_generic, ty, val))
+    }
+
+    fn charge_move_to(
+        &mut self,
+        is_generic: bool,
+        ty: impl TypeView,
+        val: impl ValueView,
+        is_success: bool,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::MoveTo(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_move_to(is_generic, ty, val, is_success))
+    }
+
+    fn charge_vec_pack<'a>(
+        &mut self,
+        ty: impl TypeView + 'a,
+        args: impl ExactSizeIterator<Item = impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::VecPack(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_vec_pack(ty, args))
+    }
+
+    fn charge_vec_len(&mut self, ty: impl TypeView) -> PartialVMResult<()> {
+        let op = ProfiledOp::VecLen(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_vec_len(ty))
+    }
+
+    fn charge_vec_borrow(
+        &mut self,
+        is_mut: bool,
+        ty: impl TypeView,
+        is_success: bool,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::VecBorrow(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_vec_borrow(is_mut, ty, is_success))
+    }
+
+    fn charge_vec_push_back(
+        &mut self,
+        ty: impl TypeView,
+        val: impl ValueView,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::VecPushBack(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_vec_push_back(ty, val))
+    }
+
+    fn charge_vec_pop_back(
+        &mut self,
+        ty: impl TypeView,
+        val: Option<impl ValueView>,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::VecPopBack(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_vec_pop_back(ty, val))
+    }
+
+    fn charge_vec_unpack(
+        &mut self,
+        ty: impl TypeView,
+        expect_num_elements: NumArgs,
+    ) -> PartialVMResult<()> {
+        let op = ProfiledOp::VecUnpack(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_vec_unpack(ty, expect_num_elements))
+    }
+
+    fn charge_vec_swap(&mut self, ty: impl TypeView) -> PartialVMResult<()> {
+        let op = ProfiledOp::VecSwap(ty.to_type_tag());
+        self.charge_and_record(op, |m| m.charge_vec_swap(ty))
+    }
+
+    fn charge_load_resource(&mut self, loaded: Option<NumBytes>) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::LoadResource, |m| m.charge_load_resource(loaded))
+    }
+
+    fn charge_native_function(&mut self, amount: InternalGas) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::NativeFunction, |m| {
+            m.charge_native_function(amount)
+        })
+    }
+
+    fn charge_native_step(&mut self, amount: InternalGas) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::NativeStep, |m| m.charge_native_step(amount))
+    }
+
+    fn charge_push(&mut self, pushed: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::Push, |m| m.charge_push(pushed))
+    }
+
+    fn charge_pop(&mut self, popped: impl ValueView) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::Pop, |m| m.charge_pop(popped))
+    }
+
+    fn charge_call_frame(
+        &mut self,
+        call_stack_height: NumArgs,
+        locals_size: AbstractMemorySize,
+    ) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::CallFrame, |m| {
+            m.charge_call_frame(call_stack_height, locals_size)
+        })
+    }
+
+    fn charge_pop_frame(&mut self, locals_size: AbstractMemorySize) -> PartialVMResult<()> {
+        self.charge_and_record(ProfiledOp::PopFrame, |m| m.charge_pop_frame(locals_size))
+    }
+
+    fn finish(self) -> GasOutputs {
+        self.inner.finish()
+    }
+
+    fn remaining_gas(&self) -> InternalGas {
+        self.inner.remaining_gas()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flat_cost(base: u64) -> GasCost {
+        GasCost::new(
+            InternalGas::new(base),
+            InternalGasPerByte::new(0),
+            InternalGasPerAbstractMemoryUnit::new(0),
+        )
+    }
+
+    /// A `CostTable` with every entry set to a flat 1-unit charge and generous limits, so tests
+    /// only need to override the one or two fields they care about.
+    fn test_cost_table() -> CostTable {
+        let one = flat_cost(1);
+        CostTable {
+            instruction_table: vec![one; NUM_SIMPLE_INSTRUCTIONS],
+            native_call: one,
+            call: one,
+            call_generic: one,
+            ld_const: one,
+            copy_loc: one,
+            move_loc: one,
+            store_loc: one,
+            pack: one,
+            unpack: one,
+            read_ref: one,
+            write_ref: one,
+            eq: one,
+            neq: one,
+            borrow_global: one,
+            exists: one,
+            move_from: one,
+            move_to: one,
+            vec_pack: one,
+            vec_len: one,
+            vec_borrow: one,
+            vec_push_back: one,
+            vec_pop_back: one,
+            vec_unpack: one,
+            vec_swap: one,
+            load_resource: one,
+            per_abstract_memory_unit: InternalGasPerAbstractMemoryUnit::new(0),
+            max_abstract_memory_in_use: AbstractMemorySize::new(1_000_000),
+            max_operand_stack_height: 1_000,
+            max_call_stack_height: 1_000,
+            storage_refund_per_abstract_memory_unit: InternalGasPerAbstractMemoryUnit::new(1),
+            max_storage_rebate_fraction_percent: 50,
+        }
+    }
+
+    #[test]
+    fn out_of_gas_at_the_balance_boundary() {
+        let mut meter = ChargingGasMeter::new(test_cost_table(), InternalGas::new(2)).unwrap();
+        assert!(meter.charge_native_step(InternalGas::new(1)).is_ok());
+        assert!(meter.charge_native_step(InternalGas::new(1)).is_ok());
+        let err = meter.charge_native_step(InternalGas::new(1)).unwrap_err();
+        assert_eq!(err.major_status(), StatusCode::OUT_OF_GAS);
+        assert_eq!(meter.balance(), InternalGas::new(0));
+    }
+
+    #[test]
+    fn short_instruction_table_is_rejected_at_construction() {
+        let mut table = test_cost_table();
+        table.instruction_table.pop();
+        assert!(ChargingGasMeter::new(table, InternalGas::new(100)).is_err());
+    }
+
+    #[test]
+    fn storage_rebate_percent_above_100_is_rejected_at_construction() {
+        let mut table = test_cost_table();
+        table.max_storage_rebate_fraction_percent = 101;
+        assert!(ChargingGasMeter::new(table, InternalGas::new(100)).is_err());
+    }
+
+    #[test]
+    fn storage_rebate_is_capped_by_total_charged() {
+        let mut meter = ChargingGasMeter::new(test_cost_table(), InternalGas::new(1_000)).unwrap();
+        // Charge a small amount of gas, then "free" a storage slot far larger than anything
+        // charged so far; the 50%-of-total-charged cap should still hold.
+        meter.charge_execution(InternalGas::new(10)).unwrap();
+        meter.accrue_storage_refund(AbstractMemorySize::new(10_000));
+        assert_eq!(meter.storage_rebate, InternalGas::new(5));
+    }
+
+    #[test]
+    fn call_stack_height_limit_is_enforced() {
+        let mut table = test_cost_table();
+        table.max_call_stack_height = 1;
+        let mut meter = ChargingGasMeter::new(table, InternalGas::new(1_000)).unwrap();
+        assert!(meter
+            .charge_call_frame(NumArgs::new(1), AbstractMemorySize::new(1))
+            .is_ok());
+        let err = meter
+            .charge_call_frame(NumArgs::new(2), AbstractMemorySize::new(1))
+            .unwrap_err();
+        assert_eq!(err.major_status(), StatusCode::OUT_OF_GAS);
+    }
+
+    #[test]
+    fn sequential_non_nested_calls_release_frame_memory() {
+        // Each call's locals are released by `charge_pop_frame` on return, so a long run of
+        // sequential (not recursive) calls should never ratchet `abstract_memory_in_use` up
+        // across the whole session -- only nested/concurrently-live frames should count.
+        let mut table = test_cost_table();
+        table.max_abstract_memory_in_use = AbstractMemorySize::new(100);
+        let mut meter = ChargingGasMeter::new(table, InternalGas::new(1_000_000)).unwrap();
+        for _ in 0..1_000 {
+            meter
+                .charge_call_frame(NumArgs::new(1), AbstractMemorySize::new(50))
+                .unwrap();
+            meter.charge_pop_frame(AbstractMemorySize::new(50)).unwrap();
+        }
+        assert_eq!(meter.abstract_memory_in_use, AbstractMemorySize::new(0));
+    }
 }